@@ -0,0 +1,156 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::sync::{Arc, Mutex};
+use warp::{Filter, Rejection};
+
+#[derive(Debug)]
+pub struct Unauthorized;
+impl warp::reject::Reject for Unauthorized {}
+
+#[derive(Debug)]
+pub struct TooManyInFlight;
+impl warp::reject::Reject for TooManyInFlight {}
+
+/// Holds the configured bearer tokens (if any) and an in-flight request
+/// counter per token, capped at `max_in_flight_per_token` so a single token
+/// can be kept from holding every `num_threads` semaphore permit at once.
+pub struct AuthState {
+    tokens: HashSet<String>,
+    max_in_flight_per_token: usize,
+    in_flight: Mutex<HashMap<String, usize>>,
+}
+
+impl AuthState {
+    /// Reads the `AUTH_TOKEN` env var (a single token or a comma-separated
+    /// set). An unset or empty value disables auth entirely.
+    pub fn from_env(max_in_flight_per_token: usize) -> Arc<Self> {
+        let tokens = env::var("AUTH_TOKEN")
+            .ok()
+            .map(|raw| {
+                raw.split(',')
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Arc::new(Self {
+            tokens,
+            max_in_flight_per_token,
+            in_flight: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    fn try_acquire(&self, token: &str) -> bool {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let count = in_flight.entry(token.to_string()).or_insert(0);
+        if *count >= self.max_in_flight_per_token {
+            false
+        } else {
+            *count += 1;
+            true
+        }
+    }
+
+    fn release(&self, token: &str) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(count) = in_flight.get_mut(token) {
+            *count = count.saturating_sub(1);
+        }
+    }
+}
+
+/// RAII guard that releases its token's in-flight slot when the request
+/// handler finishes, whichever way it finishes.
+pub enum TokenGuard {
+    Tracked(Arc<AuthState>, String),
+    Untracked,
+}
+
+impl Drop for TokenGuard {
+    fn drop(&mut self) {
+        if let TokenGuard::Tracked(state, token) = self {
+            state.release(token);
+        }
+    }
+}
+
+impl TokenGuard {
+    /// The authenticated token identity, or `None` when auth is disabled.
+    pub fn token(&self) -> Option<&str> {
+        match self {
+            TokenGuard::Tracked(_, token) => Some(token),
+            TokenGuard::Untracked => None,
+        }
+    }
+}
+
+async fn authenticate(header: Option<String>, state: Arc<AuthState>) -> Result<TokenGuard, Rejection> {
+    if !state.enabled() {
+        return Ok(TokenGuard::Untracked);
+    }
+
+    let token = header
+        .and_then(|h| h.strip_prefix("Bearer ").map(|t| t.to_string()))
+        .ok_or_else(|| warp::reject::custom(Unauthorized))?;
+
+    if !state.tokens.contains(&token) {
+        return Err(warp::reject::custom(Unauthorized));
+    }
+
+    if state.try_acquire(&token) {
+        Ok(TokenGuard::Tracked(state, token))
+    } else {
+        Err(warp::reject::custom(TooManyInFlight))
+    }
+}
+
+/// Extracts and validates the `Authorization: Bearer <token>` header,
+/// producing a `TokenGuard` that tracks the token's in-flight requests for
+/// the lifetime of the handler.
+pub fn with_auth(state: Arc<AuthState>) -> impl Filter<Extract = (TokenGuard,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::any().map(move || state.clone()))
+        .and_then(authenticate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with_cap(max_in_flight_per_token: usize) -> AuthState {
+        AuthState {
+            tokens: ["tok".to_string()].into_iter().collect(),
+            max_in_flight_per_token,
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[test]
+    fn try_acquire_rejects_once_the_per_token_cap_is_hit() {
+        let state = state_with_cap(2);
+        assert!(state.try_acquire("tok"));
+        assert!(state.try_acquire("tok"));
+        assert!(!state.try_acquire("tok"));
+    }
+
+    #[test]
+    fn release_frees_a_slot_for_reuse() {
+        let state = state_with_cap(1);
+        assert!(state.try_acquire("tok"));
+        assert!(!state.try_acquire("tok"));
+        state.release("tok");
+        assert!(state.try_acquire("tok"));
+    }
+
+    #[test]
+    fn release_of_an_untracked_token_is_a_no_op() {
+        let state = state_with_cap(1);
+        state.release("never-acquired");
+        assert!(state.try_acquire("tok"));
+    }
+}
@@ -3,7 +3,9 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use scryer_prolog::{MachineBuilder, StreamConfig, LeafAnswer, Term};
 use std::{collections::BTreeMap, sync::Arc};
-use tokio::sync::Semaphore;
+use tokio::sync::{mpsc, Semaphore};
+use tokio_stream::wrappers::ReceiverStream;
+use warp::hyper::{Body, body::Bytes};
 use dotenvy::dotenv;
 use std::env;
 use log::{info, warn, error};
@@ -12,15 +14,66 @@ use once_cell::sync::OnceCell;
 
 use std::fs;
 use std::path::Path;
+
+mod auth;
+mod metrics;
+mod program_store;
+mod sessions;
+use auth::{AuthState, TokenGuard};
+use metrics::Metrics;
+use program_store::ProgramStore;
+use sessions::{SessionLimitReached, SessionRegistry};
+use std::time::Duration;
+use warp::http::StatusCode;
+
+// Bound on the ndjson channel so a slow client applies backpressure to the
+// blocking producer instead of letting it race ahead and buffer in memory.
+const STREAM_CHANNEL_CAPACITY: usize = 32;
+
 #[derive(Debug, Deserialize)]
 struct QueryRequest {
+    #[serde(default)]
+    program: Option<String>,
+    query: String,
+    #[serde(default)]
+    program_id: Option<String>,
+    /// Aborts the goal after this many logical inferences (via
+    /// `call_with_inference_limit/3`) so a runaway query can't pin a worker
+    /// forever.
+    #[serde(default)]
+    max_inferences: Option<u64>,
+    /// Stops collecting answers once this many have been produced and marks
+    /// the response as truncated.
+    #[serde(default)]
+    max_results: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoreProgramRequest {
+    name: String,
+    program: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateSessionRequest {
+    #[serde(default)]
     program: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionQueryRequest {
     query: String,
+    #[serde(default)]
+    max_inferences: Option<u64>,
+    #[serde(default)]
+    max_results: Option<usize>,
 }
 
 #[derive(Debug, Serialize)]
 struct QueryResponse {
     results: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    truncated: Option<serde_json::Value>,
 }
 
 #[tokio::main(flavor = "multi_thread")]
@@ -43,20 +96,141 @@ async fn main() {
 
     info!("🚀 Prolog service starting with {num_threads} threads...");
     info!("🌍 Listening on http://0.0.0.0:{port}/query");
+    info!("🌊 Streaming queries available at http://0.0.0.0:{port}/query/stream");
 
     let semaphore = Arc::new(Semaphore::new(num_threads));
 
+    let program_store_path = env::var("PROGRAM_STORE_PATH").unwrap_or_else(|_| "./data/programs".to_string());
+    let program_store = ProgramStore::open(&program_store_path)
+        .expect("Failed to open program store");
+    info!("🗄️ Program store opened at {program_store_path}");
+
+    // Cap a single token below num_threads so it can't hold every permit; on a
+    // single-thread host the cap is 1, which is every permit.
+    let auth_state = AuthState::from_env(num_threads.saturating_sub(1).max(1));
+    if auth_state.enabled() {
+        info!("🔐 Bearer token authentication enabled");
+    } else {
+        warn!("🔓 AUTH_TOKEN not set; /query endpoints are unauthenticated");
+    }
+
+    let metrics = Metrics::new(semaphore.clone(), num_threads);
+
+    // Each session pins a worker thread until the idle reaper collects it, so
+    // both caps exist to stop a handful of tokens from exhausting threads.
+    let max_sessions: usize = env::var("MAX_SESSIONS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(100);
+    let max_sessions_per_token: usize = env::var("MAX_SESSIONS_PER_TOKEN")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(20);
+    let session_registry = SessionRegistry::new(max_sessions, max_sessions_per_token);
+    let session_ttl_secs: u64 = env::var("SESSION_TTL_SECONDS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(600);
+    {
+        let session_registry = session_registry.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                session_registry.reap_idle(Duration::from_secs(session_ttl_secs));
+            }
+        });
+    }
+    info!("🗂️ Sessions idle-expire after {session_ttl_secs}s (max {max_sessions} total, {max_sessions_per_token} per token)");
+
     let query_route = warp::path("query")
+        .and(warp::path::end())
         .and(warp::post())
         .and(warp::body::json())
         .and(with_semaphore(semaphore.clone()))
+        .and(with_db(program_store.clone()))
+        .and(with_metrics(metrics.clone()))
+        .and(auth::with_auth(auth_state.clone()))
         .and_then(handle_query);
 
+    let query_stream_route = warp::path!("query" / "stream")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_semaphore(semaphore.clone()))
+        .and(with_db(program_store.clone()))
+        .and(with_metrics(metrics.clone()))
+        .and(auth::with_auth(auth_state.clone()))
+        .and_then(handle_query_stream);
+
+    let store_program_route = warp::path("programs")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_db(program_store.clone()))
+        .and(auth::with_auth(auth_state.clone()))
+        .and_then(handle_store_program);
+
+    let get_program_route = warp::path!("programs" / String)
+        .and(warp::get())
+        .and(with_db(program_store.clone()))
+        .and(auth::with_auth(auth_state.clone()))
+        .and_then(handle_get_program);
+
+    let delete_program_route = warp::path!("programs" / String)
+        .and(warp::delete())
+        .and(with_db(program_store.clone()))
+        .and(auth::with_auth(auth_state.clone()))
+        .and_then(handle_delete_program);
+
+    let create_session_route = warp::path("sessions")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_sessions(session_registry.clone()))
+        .and(with_metrics(metrics.clone()))
+        .and(auth::with_auth(auth_state.clone()))
+        .and_then(handle_create_session);
+
+    let session_query_route = warp::path!("sessions" / String / "query")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_sessions(session_registry.clone()))
+        .and(with_metrics(metrics.clone()))
+        .and(auth::with_auth(auth_state.clone()))
+        .and_then(handle_session_query);
+
+    let delete_session_route = warp::path!("sessions" / String)
+        .and(warp::delete())
+        .and(with_sessions(session_registry.clone()))
+        .and(auth::with_auth(auth_state.clone()))
+        .and_then(handle_delete_session);
+
     let health_route = warp::path("health")
         .and(warp::get())
         .map(|| warp::reply::json(&json!({ "status": "ok" })));
 
-    let routes = query_route.or(health_route);
+    // Gated behind the same bearer auth as every other route: counters
+    // expose query volume and latency, which isn't safe to hand to an
+    // unauthenticated caller whenever AUTH_TOKEN is set.
+    let metrics_route = warp::path("metrics")
+        .and(warp::get())
+        .and(with_metrics(metrics.clone()))
+        .and(auth::with_auth(auth_state.clone()))
+        .map(|metrics: Arc<Metrics>, _guard: TokenGuard| {
+            warp::reply::with_header(metrics.render(), "Content-Type", "text/plain; version=0.0.4")
+        });
+
+    let routes = query_route
+        .or(query_stream_route)
+        .or(store_program_route)
+        .or(get_program_route)
+        .or(delete_program_route)
+        .or(create_session_route)
+        .or(session_query_route)
+        .or(delete_session_route)
+        .or(health_route)
+        .or(metrics_route)
+        .recover(handle_rejection);
 
     // --- 🌙 Graceful shutdown ---
     let (_, server) = warp::serve(routes)
@@ -77,26 +251,227 @@ fn with_semaphore(
     warp::any().map(move || sem.clone())
 }
 
+fn with_db(
+    db: ProgramStore,
+) -> impl Filter<Extract = (ProgramStore,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || db.clone())
+}
+
+fn with_metrics(
+    metrics: Arc<Metrics>,
+) -> impl Filter<Extract = (Arc<Metrics>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || metrics.clone())
+}
+
+fn with_sessions(
+    sessions: SessionRegistry,
+) -> impl Filter<Extract = (SessionRegistry,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || sessions.clone())
+}
+
+/// Turns auth rejections into the right HTTP status instead of warp's
+/// default 500, leaving unrelated rejections for the default handler.
+async fn handle_rejection(err: warp::Rejection) -> Result<impl warp::Reply, warp::Rejection> {
+    let (status, message) = if err.find::<auth::Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED, "Missing or invalid bearer token")
+    } else if err.find::<auth::TooManyInFlight>().is_some() {
+        (StatusCode::TOO_MANY_REQUESTS, "Too many in-flight requests for this token")
+    } else if err.find::<SessionLimitReached>().is_some() {
+        (StatusCode::TOO_MANY_REQUESTS, "Maximum concurrent session limit reached")
+    } else if err.is_not_found() {
+        (StatusCode::NOT_FOUND, "Not found")
+    } else {
+        return Err(err);
+    };
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&json!({ "error": message })),
+        status,
+    ))
+}
+
+/// Resolves the program source a `QueryRequest` should run against: either
+/// the inline `program` text or, if `program_id` was given instead, the
+/// source previously saved via `POST /programs`. The sled lookup runs on the
+/// blocking pool like every other program-store access.
+async fn resolve_program(req: &QueryRequest, db: &ProgramStore) -> Result<String, String> {
+    if let Some(program_id) = &req.program_id {
+        let db = db.clone();
+        let id = program_id.clone();
+        let stored = tokio::task::spawn_blocking(move || db.get(&id))
+            .await
+            .map_err(|e| format!("Task join error: {e}"))?
+            .map_err(|e| format!("Program store error: {e}"))?
+            .ok_or_else(|| format!("No program stored under id '{program_id}'"))?;
+        return Ok(stored.program);
+    }
+
+    req.program
+        .clone()
+        .ok_or_else(|| "Request must set either 'program' or 'program_id'".to_string())
+}
+
+async fn handle_store_program(
+    req: StoreProgramRequest,
+    db: ProgramStore,
+    _guard: TokenGuard,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let name = req.name.clone();
+    let result = tokio::task::spawn_blocking(move || db.put(&req.name, req.program)).await;
+
+    match result {
+        Ok(Ok(stored)) => {
+            info!("🗄️ Stored program '{}' (version {})", name, stored.version);
+            Ok(warp::reply::json(&json!({
+                "id": name,
+                "version": stored.version,
+            })))
+        }
+        Ok(Err(e)) => {
+            error!("❌ Failed to store program '{}': {}", name, e);
+            Ok(warp::reply::json(&json!({ "error": format!("{e}") })))
+        }
+        Err(join_err) => {
+            error!("❌ Task join error: {:?}", join_err);
+            Ok(warp::reply::json(&json!({ "error": format!("Task join error: {join_err}") })))
+        }
+    }
+}
+
+async fn handle_get_program(name: String, db: ProgramStore, _guard: TokenGuard) -> Result<impl warp::Reply, warp::Rejection> {
+    let lookup_name = name.clone();
+    let result = tokio::task::spawn_blocking(move || db.get(&lookup_name)).await;
+
+    match result {
+        Ok(Ok(Some(stored))) => Ok(warp::reply::json(&json!({
+            "id": name,
+            "program": stored.program,
+            "version": stored.version,
+        }))),
+        Ok(Ok(None)) => Ok(warp::reply::json(&json!({ "error": format!("No program stored under id '{name}'") }))),
+        Ok(Err(e)) => {
+            error!("❌ Failed to read program '{}': {}", name, e);
+            Ok(warp::reply::json(&json!({ "error": format!("{e}") })))
+        }
+        Err(join_err) => {
+            error!("❌ Task join error: {:?}", join_err);
+            Ok(warp::reply::json(&json!({ "error": format!("Task join error: {join_err}") })))
+        }
+    }
+}
+
+async fn handle_delete_program(name: String, db: ProgramStore, _guard: TokenGuard) -> Result<impl warp::Reply, warp::Rejection> {
+    let delete_name = name.clone();
+    let result = tokio::task::spawn_blocking(move || db.delete(&delete_name)).await;
+
+    match result {
+        Ok(Ok(true)) => {
+            info!("🗑️ Deleted program '{}'", name);
+            Ok(warp::reply::json(&json!({ "deleted": true })))
+        }
+        Ok(Ok(false)) => Ok(warp::reply::json(&json!({ "deleted": false, "error": format!("No program stored under id '{name}'") }))),
+        Ok(Err(e)) => {
+            error!("❌ Failed to delete program '{}': {}", name, e);
+            Ok(warp::reply::json(&json!({ "error": format!("{e}") })))
+        }
+        Err(join_err) => {
+            error!("❌ Task join error: {:?}", join_err);
+            Ok(warp::reply::json(&json!({ "error": format!("Task join error: {join_err}") })))
+        }
+    }
+}
+
+async fn handle_create_session(
+    req: CreateSessionRequest,
+    sessions: SessionRegistry,
+    metrics: Arc<Metrics>,
+    guard: TokenGuard,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let owner = guard.token().map(|t| t.to_string());
+    match sessions.create(req.program, metrics, owner) {
+        Ok(id) => {
+            info!("🗂️ Created session '{}'", id);
+            Ok(warp::reply::json(&json!({ "id": id })))
+        }
+        Err(_) => Err(warp::reject::custom(SessionLimitReached)),
+    }
+}
+
+async fn handle_session_query(
+    session_id: String,
+    req: SessionQueryRequest,
+    sessions: SessionRegistry,
+    metrics: Arc<Metrics>,
+    _guard: TokenGuard,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let Some(session) = sessions.get(&session_id) else {
+        return Ok(warp::reply::json(&json!({ "error": format!("No session '{session_id}'") })));
+    };
+
+    metrics.record_query_start();
+    let start = std::time::Instant::now();
+    let result = session.query(req.query, req.max_inferences, req.max_results).await;
+    metrics.record_latency(start.elapsed());
+
+    match result {
+        Ok((results, truncated_limit)) => {
+            let truncated = truncated_limit.map(|limit| json!({ "truncated": true, "limit": limit }));
+            Ok(warp::reply::json(&QueryResponse { results, truncated }))
+        }
+        Err(err_msg) => {
+            error!("❌ Session query error: {}", err_msg);
+            Ok(warp::reply::json(&json!({ "error": err_msg })))
+        }
+    }
+}
+
+async fn handle_delete_session(
+    session_id: String,
+    sessions: SessionRegistry,
+    _guard: TokenGuard,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let deleted = sessions.remove(&session_id);
+    if deleted {
+        info!("🗑️ Deleted session '{}'", session_id);
+    }
+    Ok(warp::reply::json(&json!({ "deleted": deleted })))
+}
+
 async fn handle_query(
     req: QueryRequest,
     semaphore: Arc<Semaphore>,
+    db: ProgramStore,
+    metrics: Arc<Metrics>,
+    _guard: TokenGuard,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let _permit = semaphore.acquire_owned().await.unwrap();
 
-    let program: Arc<String> = Arc::new(req.program);
+    let program = match resolve_program(&req, &db).await {
+        Ok(program) => Arc::new(program),
+        Err(err_msg) => return Ok(warp::reply::json(&json!({ "error": err_msg }))),
+    };
     let query: Arc<String> = Arc::new(req.query);
+    let max_inferences = req.max_inferences;
+    let max_results = req.max_results;
 
     info!("🧩 Handling query: {}", query);
+    metrics.record_query_start();
 
+    let start = std::time::Instant::now();
     let res = tokio::task::spawn_blocking({
         let program = Arc::clone(&program);
         let query = Arc::clone(&query);
-        move || run_query(&program, &query)
+        let metrics = Arc::clone(&metrics);
+        move || run_query(&program, &query, &metrics, max_inferences, max_results)
     })
         .await;
+    metrics.record_latency(start.elapsed());
 
     match res {
-        Ok(Ok(results)) => Ok(warp::reply::json(&QueryResponse { results })),
+        Ok(Ok((results, truncated_limit))) => {
+            let truncated = truncated_limit.map(|limit| json!({ "truncated": true, "limit": limit }));
+            Ok(warp::reply::json(&QueryResponse { results, truncated }))
+        }
         Ok(Err(err_msg)) => {
             error!("❌ Query error: {}", err_msg);
             Ok(warp::reply::json(&json!({ "error": err_msg })))
@@ -110,30 +485,175 @@ async fn handle_query(
     }
 }
 
-fn run_query(program: &str, query: &str) -> Result<Vec<serde_json::Value>, String> {
+/// Streaming counterpart to `handle_query`: answers are emitted one at a time
+/// as newline-delimited JSON instead of being buffered into a `Vec` first.
+async fn handle_query_stream(
+    req: QueryRequest,
+    semaphore: Arc<Semaphore>,
+    db: ProgramStore,
+    metrics: Arc<Metrics>,
+    guard: TokenGuard,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let permit = semaphore.acquire_owned().await.unwrap();
+
+    let program = match resolve_program(&req, &db).await {
+        Ok(program) => Arc::new(program),
+        Err(err_msg) => {
+            let body = Body::from(json!({ "error": err_msg }).to_string());
+            return Ok(warp::http::Response::builder()
+                .header("Content-Type", "application/json")
+                .body(body)
+                .unwrap());
+        }
+    };
+    let query: Arc<String> = Arc::new(req.query);
+    let max_inferences = req.max_inferences;
+    let max_results = req.max_results;
+
+    info!("🧩 Streaming query: {}", query);
+    metrics.record_query_start();
+
+    let (tx, rx) = mpsc::channel::<Result<Bytes, std::io::Error>>(STREAM_CHANNEL_CAPACITY);
+
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit; // held for the lifetime of the blocking producer
+        let _guard = guard; // ditto for the per-token in-flight slot
+        run_query_stream(&program, &query, tx, &metrics, max_inferences, max_results);
+    });
+
+    let body = Body::wrap_stream(ReceiverStream::new(rx));
+
+    Ok(warp::http::Response::builder()
+        .header("Content-Type", "application/x-ndjson")
+        .body(body)
+        .unwrap())
+}
+
+/// Runs `query` against `program` on the current (blocking) thread, pushing
+/// one ndjson line per `LeafAnswer` into `tx` as it is produced. Exits early
+/// if the receiver is dropped, e.g. because the client disconnected.
+fn run_query_stream(
+    program: &str,
+    query: &str,
+    tx: mpsc::Sender<Result<Bytes, std::io::Error>>,
+    metrics: &Metrics,
+    max_inferences: Option<u64>,
+    max_results: Option<usize>,
+) {
+    let streams = StreamConfig::in_memory();
+    let mut machine = MachineBuilder::new().with_streams(streams).build();
+
+    machine.consult_module_string("user", program);
+
+    let goal = bound_goal(query, max_inferences);
+    let query_iter = machine.run_query(&goal);
+    let mut produced = 0usize;
+
+    for answer in query_iter {
+        if let Some(limit) = max_results {
+            if produced >= limit {
+                let marker = json!({ "truncated": true, "limit": limit }).to_string() + "\n";
+                let _ = tx.blocking_send(Ok(Bytes::from(marker)));
+                return;
+            }
+        }
+
+        let value = match answer {
+            Ok(LeafAnswer::True) => {
+                metrics.record_answer_true();
+                json!({ "result": true })
+            }
+            Ok(LeafAnswer::False) => {
+                metrics.record_answer_false();
+                json!({ "result": false })
+            }
+            Ok(LeafAnswer::Exception(term)) => {
+                metrics.record_answer_exception();
+                json!({ "exception": term_to_json(&term) })
+            }
+            Ok(LeafAnswer::LeafAnswer { bindings, .. }) => convert_bindings_to_json(bindings),
+            Err(e) => {
+                metrics.record_answer_error();
+                json!({ "error": format!("{:?}", e) })
+            }
+        };
+
+        let mut line = value.to_string();
+        line.push('\n');
+        produced += 1;
+
+        if tx.blocking_send(Ok(Bytes::from(line))).is_err() {
+            warn!("🔌 Stream receiver dropped; stopping query early");
+            return;
+        }
+    }
+}
+
+/// Wraps `query` in `call_with_inference_limit/3` when a limit is set, so
+/// scryer aborts the goal after `limit` logical inferences instead of
+/// letting a runaway generator pin the worker forever. Strips a trailing
+/// clause terminator first — callers may send a query the same way they'd
+/// write consult text, with a trailing `.`, and leaving it in would land
+/// the period inside the outer parens and produce a malformed term.
+fn bound_goal(query: &str, max_inferences: Option<u64>) -> String {
+    match max_inferences {
+        Some(limit) => {
+            let goal = query.trim().trim_end_matches('.');
+            format!("call_with_inference_limit(({goal}), {limit}, _)")
+        }
+        None => query.to_string(),
+    }
+}
+
+fn run_query(
+    program: &str,
+    query: &str,
+    metrics: &Metrics,
+    max_inferences: Option<u64>,
+    max_results: Option<usize>,
+) -> Result<(Vec<serde_json::Value>, Option<usize>), String> {
     let streams = StreamConfig::in_memory();
     let mut machine = MachineBuilder::new().with_streams(streams).build();
 
     machine.consult_module_string("user", program);
 
-    let query_iter = machine.run_query(query);
+    let goal = bound_goal(query, max_inferences);
+    let query_iter = machine.run_query(&goal);
     let mut results = Vec::new();
+    let mut truncated_limit = None;
 
     for answer in query_iter {
+        if let Some(limit) = max_results {
+            if results.len() >= limit {
+                truncated_limit = Some(limit);
+                break;
+            }
+        }
+
         match answer {
-            Ok(LeafAnswer::True) => results.push(json!({ "result": true })),
-            Ok(LeafAnswer::False) => results.push(json!({ "result": false })),
+            Ok(LeafAnswer::True) => {
+                metrics.record_answer_true();
+                results.push(json!({ "result": true }));
+            }
+            Ok(LeafAnswer::False) => {
+                metrics.record_answer_false();
+                results.push(json!({ "result": false }));
+            }
             Ok(LeafAnswer::Exception(term)) => {
-                results.push(json!({ "exception": term_to_json(&term) }))
+                metrics.record_answer_exception();
+                results.push(json!({ "exception": term_to_json(&term) }));
             }
             Ok(LeafAnswer::LeafAnswer { bindings, .. }) => {
                 results.push(convert_bindings_to_json(bindings))
             }
-            Err(e) => results.push(json!({ "error": format!("{:?}", e) })),
+            Err(e) => {
+                metrics.record_answer_error();
+                results.push(json!({ "error": format!("{:?}", e) }));
+            }
         }
     }
 
-    Ok(results)
+    Ok((results, truncated_limit))
 }
 
 fn convert_bindings_to_json(bindings: BTreeMap<String, Term>) -> serde_json::Value {
@@ -225,4 +745,47 @@ pub fn init_logger() {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_metrics() -> Arc<Metrics> {
+        Metrics::new(Arc::new(Semaphore::new(1)), 1)
+    }
+
+    #[test]
+    fn bound_goal_strips_a_trailing_full_stop_before_wrapping() {
+        let goal = bound_goal("member(X, [1,2,3]).", Some(1000));
+        assert_eq!(goal, "call_with_inference_limit((member(X, [1,2,3])), 1000, _)");
+    }
+
+    #[test]
+    fn bound_goal_leaves_a_query_without_a_terminator_untouched() {
+        let goal = bound_goal("member(X, [1,2,3])", Some(1000));
+        assert_eq!(goal, "call_with_inference_limit((member(X, [1,2,3])), 1000, _)");
+    }
+
+    #[test]
+    fn max_inferences_bounds_a_goal_that_would_otherwise_run_forever() {
+        let metrics = test_metrics();
+        let program = "count_up(N) :- N1 is N + 1, count_up(N1).";
+
+        // Without the cap this recurses forever; call_with_inference_limit
+        // must make run_query return instead of hanging the test.
+        let (results, _) = run_query(program, "count_up(0).", &metrics, Some(1000), None).unwrap();
+        assert!(!results.is_empty());
+    }
+
+    #[test]
+    fn max_results_truncates_before_all_answers_are_produced() {
+        let metrics = test_metrics();
+
+        let (results, truncated) =
+            run_query("", "member(X, [1,2,3,4,5])", &metrics, None, Some(2)).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(truncated, Some(2));
+    }
+}
+
 
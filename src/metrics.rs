@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+const LATENCY_BUCKETS_SECONDS: [f64; 11] =
+    [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// In-process counters rendered as Prometheus text exposition on `/metrics`.
+/// Shared through a single `Arc` the same way the semaphore and program
+/// store are, via a `with_metrics` filter.
+pub struct Metrics {
+    queries_total: AtomicU64,
+    answers_true_total: AtomicU64,
+    answers_false_total: AtomicU64,
+    answers_exception_total: AtomicU64,
+    answers_error_total: AtomicU64,
+    // Cumulative per-bucket counts (classic Prometheus histogram semantics),
+    // with one extra slot for the +Inf bucket.
+    latency_bucket_counts: Vec<AtomicU64>,
+    latency_sum_seconds: Mutex<f64>,
+    latency_count: AtomicU64,
+    semaphore: Arc<Semaphore>,
+    num_permits: usize,
+}
+
+impl Metrics {
+    pub fn new(semaphore: Arc<Semaphore>, num_permits: usize) -> Arc<Self> {
+        Arc::new(Self {
+            queries_total: AtomicU64::new(0),
+            answers_true_total: AtomicU64::new(0),
+            answers_false_total: AtomicU64::new(0),
+            answers_exception_total: AtomicU64::new(0),
+            answers_error_total: AtomicU64::new(0),
+            latency_bucket_counts: (0..=LATENCY_BUCKETS_SECONDS.len())
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+            latency_sum_seconds: Mutex::new(0.0),
+            latency_count: AtomicU64::new(0),
+            semaphore,
+            num_permits,
+        })
+    }
+
+    pub fn record_query_start(&self) {
+        self.queries_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_answer_true(&self) {
+        self.answers_true_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_answer_false(&self) {
+        self.answers_false_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_answer_exception(&self) {
+        self.answers_exception_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_answer_error(&self) {
+        self.answers_error_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records one observation of the `spawn_blocking` round trip in
+    /// `handle_query`, or of the session worker round trip in
+    /// `handle_session_query`. Streaming queries are not recorded.
+    pub fn record_latency(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+
+        for (bucket, bound) in self.latency_bucket_counts.iter().zip(LATENCY_BUCKETS_SECONDS.iter()) {
+            if seconds <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.latency_bucket_counts[LATENCY_BUCKETS_SECONDS.len()].fetch_add(1, Ordering::Relaxed);
+
+        *self.latency_sum_seconds.lock().unwrap() += seconds;
+        self.latency_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Renders all counters as Prometheus text-format exposition.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP prolog_queries_total Total number of queries received.\n");
+        out.push_str("# TYPE prolog_queries_total counter\n");
+        out.push_str(&format!("prolog_queries_total {}\n", self.queries_total.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP prolog_answers_total Answers without bindings, labeled by outcome; answers with bindings are not counted.\n");
+        out.push_str("# TYPE prolog_answers_total counter\n");
+        out.push_str(&format!("prolog_answers_total{{outcome=\"true\"}} {}\n", self.answers_true_total.load(Ordering::Relaxed)));
+        out.push_str(&format!("prolog_answers_total{{outcome=\"false\"}} {}\n", self.answers_false_total.load(Ordering::Relaxed)));
+        out.push_str(&format!("prolog_answers_total{{outcome=\"exception\"}} {}\n", self.answers_exception_total.load(Ordering::Relaxed)));
+        out.push_str(&format!("prolog_answers_total{{outcome=\"error\"}} {}\n", self.answers_error_total.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP prolog_query_latency_seconds Latency of buffered /query and session queries.\n");
+        out.push_str("# TYPE prolog_query_latency_seconds histogram\n");
+        for (bound, bucket) in LATENCY_BUCKETS_SECONDS.iter().zip(self.latency_bucket_counts.iter()) {
+            out.push_str(&format!(
+                "prolog_query_latency_seconds_bucket{{le=\"{bound}\"}} {}\n",
+                bucket.load(Ordering::Relaxed)
+            ));
+        }
+        out.push_str(&format!(
+            "prolog_query_latency_seconds_bucket{{le=\"+Inf\"}} {}\n",
+            self.latency_bucket_counts[LATENCY_BUCKETS_SECONDS.len()].load(Ordering::Relaxed)
+        ));
+        out.push_str(&format!("prolog_query_latency_seconds_sum {}\n", *self.latency_sum_seconds.lock().unwrap()));
+        out.push_str(&format!("prolog_query_latency_seconds_count {}\n", self.latency_count.load(Ordering::Relaxed)));
+
+        let permits_in_use = self.num_permits.saturating_sub(self.semaphore.available_permits());
+        out.push_str("# HELP prolog_semaphore_permits_in_use Query permits currently checked out.\n");
+        out.push_str("# TYPE prolog_semaphore_permits_in_use gauge\n");
+        out.push_str(&format!("prolog_semaphore_permits_in_use {permits_in_use}\n"));
+        out.push_str("# HELP prolog_semaphore_permits_total Configured query concurrency limit.\n");
+        out.push_str("# TYPE prolog_semaphore_permits_total gauge\n");
+        out.push_str(&format!("prolog_semaphore_permits_total {}\n", self.num_permits));
+
+        out
+    }
+}
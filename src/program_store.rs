@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// A single stored program: the source text plus a monotonically
+/// increasing version bumped on every overwrite of the same name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredProgram {
+    pub program: String,
+    pub version: u64,
+}
+
+/// Thin wrapper around a `sled::Db` keyed by program name. Values are
+/// serde-serialized `StoredProgram`s, following the same typed-key /
+/// serialized-value shape as a sled `Tree`.
+#[derive(Clone)]
+pub struct ProgramStore {
+    db: Arc<sled::Db>,
+}
+
+impl ProgramStore {
+    pub fn open(path: &str) -> sled::Result<Self> {
+        let db = sled::open(path)?;
+        Ok(Self { db: Arc::new(db) })
+    }
+
+    /// Inserts or overwrites the program stored under `name`, bumping its
+    /// version, and returns the stored record. Uses `update_and_fetch` so the
+    /// read-modify-write of the version counter is atomic within sled —
+    /// two concurrent `put`s for the same name can't both observe the same
+    /// old version and stamp the same new one.
+    pub fn put(&self, name: &str, program: String) -> sled::Result<StoredProgram> {
+        let updated = self.db.update_and_fetch(name.as_bytes(), move |old| {
+            let version = old
+                .and_then(|bytes| serde_json::from_slice::<StoredProgram>(bytes).ok())
+                .map(|existing| existing.version + 1)
+                .unwrap_or(1);
+            let stored = StoredProgram { program: program.clone(), version };
+            Some(serde_json::to_vec(&stored).expect("StoredProgram always serializes"))
+        })?;
+        self.db.flush()?;
+
+        let bytes = updated.expect("update_and_fetch always sets a value");
+        Ok(serde_json::from_slice(&bytes).expect("stored program is valid JSON"))
+    }
+
+    pub fn get(&self, name: &str) -> sled::Result<Option<StoredProgram>> {
+        let Some(bytes) = self.db.get(name.as_bytes())? else {
+            return Ok(None);
+        };
+        let stored = serde_json::from_slice(&bytes).expect("stored program is valid JSON");
+        Ok(Some(stored))
+    }
+
+    pub fn delete(&self, name: &str) -> sled::Result<bool> {
+        let removed = self.db.remove(name.as_bytes())?;
+        self.db.flush()?;
+        Ok(removed.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store() -> ProgramStore {
+        let db = sled::Config::new()
+            .temporary(true)
+            .open()
+            .expect("open temporary sled db");
+        ProgramStore { db: Arc::new(db) }
+    }
+
+    #[test]
+    fn put_starts_at_version_one_and_increments_on_overwrite() {
+        let store = temp_store();
+
+        let first = store.put("prog", "true.".to_string()).unwrap();
+        assert_eq!(first.version, 1);
+
+        let second = store.put("prog", "false.".to_string()).unwrap();
+        assert_eq!(second.version, 2);
+        assert_eq!(second.program, "false.");
+    }
+
+    #[test]
+    fn get_of_missing_name_is_none() {
+        let store = temp_store();
+        assert!(store.get("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn delete_reports_whether_something_was_removed() {
+        let store = temp_store();
+        store.put("prog", "true.".to_string()).unwrap();
+
+        assert!(store.delete("prog").unwrap());
+        assert!(!store.delete("prog").unwrap());
+        assert!(store.get("prog").unwrap().is_none());
+    }
+}
@@ -0,0 +1,267 @@
+use scryer_prolog::{LeafAnswer, Machine, MachineBuilder, StreamConfig};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc as std_mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::metrics::Metrics;
+use crate::{bound_goal, convert_bindings_to_json, term_to_json};
+
+/// Rejected in `SessionRegistry::create` when either the global or
+/// per-token concurrent session cap is already at its limit.
+#[derive(Debug)]
+pub struct SessionLimitReached;
+impl warp::reject::Reject for SessionLimitReached {}
+
+enum SessionCommand {
+    Query {
+        query: String,
+        max_inferences: Option<u64>,
+        max_results: Option<usize>,
+        respond_to: oneshot::Sender<Result<(Vec<Value>, Option<usize>), String>>,
+    },
+}
+
+/// One live, interactive session: a `Machine` pinned to a dedicated OS
+/// thread so `assertz`/`retract` and consulted clauses persist across
+/// requests, driven by commands sent over a channel.
+pub struct SessionHandle {
+    commands: std_mpsc::Sender<SessionCommand>,
+    last_used_unix: AtomicU64,
+    owner: Option<String>,
+}
+
+impl SessionHandle {
+    fn touch(&self) {
+        self.last_used_unix.store(now_unix(), Ordering::Relaxed);
+    }
+
+    fn idle_for(&self) -> Duration {
+        Duration::from_secs(now_unix().saturating_sub(self.last_used_unix.load(Ordering::Relaxed)))
+    }
+
+    pub async fn query(
+        &self,
+        query: String,
+        max_inferences: Option<u64>,
+        max_results: Option<usize>,
+    ) -> Result<(Vec<Value>, Option<usize>), String> {
+        self.touch();
+
+        let (respond_to, response) = oneshot::channel();
+        self.commands
+            .send(SessionCommand::Query { query, max_inferences, max_results, respond_to })
+            .map_err(|_| "Session worker has stopped".to_string())?;
+
+        response
+            .await
+            .map_err(|_| "Session worker dropped the response channel".to_string())?
+    }
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Registry of live sessions, keyed by id and shared through a
+/// `with_sessions` filter the same way the program store and semaphore are.
+/// Bounds both the total number of live sessions and how many a single
+/// token may hold at once, since each session pins a worker thread until
+/// the idle reaper collects it.
+#[derive(Clone)]
+pub struct SessionRegistry {
+    sessions: Arc<Mutex<HashMap<String, Arc<SessionHandle>>>>,
+    max_sessions: usize,
+    max_sessions_per_token: usize,
+}
+
+impl SessionRegistry {
+    pub fn new(max_sessions: usize, max_sessions_per_token: usize) -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            max_sessions,
+            max_sessions_per_token,
+        }
+    }
+
+    /// Spawns a dedicated worker thread holding a `Machine` consulted with
+    /// `program`, and registers a handle to it under a fresh session id.
+    /// Rejects the request once the global or per-token session cap is hit.
+    ///
+    /// The cap checks and the registry insert happen under one lock
+    /// acquisition (the same shape as `AuthState::try_acquire`), so a burst
+    /// of concurrent callers can't all pass the check before any of them
+    /// inserts and blow past the cap.
+    pub fn create(
+        &self,
+        program: String,
+        metrics: Arc<Metrics>,
+        owner: Option<String>,
+    ) -> Result<String, SessionLimitReached> {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        if sessions.len() >= self.max_sessions {
+            return Err(SessionLimitReached);
+        }
+        if let Some(token) = &owner {
+            let owned = sessions
+                .values()
+                .filter(|handle| handle.owner.as_deref() == Some(token.as_str()))
+                .count();
+            if owned >= self.max_sessions_per_token {
+                return Err(SessionLimitReached);
+            }
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let (tx, rx) = std_mpsc::channel::<SessionCommand>();
+
+        std::thread::Builder::new()
+            .name(format!("prolog-session-{id}"))
+            .spawn(move || session_worker(program, rx, metrics))
+            .expect("Failed to spawn session worker thread");
+
+        let handle = Arc::new(SessionHandle {
+            commands: tx,
+            last_used_unix: AtomicU64::new(now_unix()),
+            owner,
+        });
+
+        sessions.insert(id.clone(), handle);
+        Ok(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<SessionHandle>> {
+        self.sessions.lock().unwrap().get(id).cloned()
+    }
+
+    /// Drops the handle, which drops its command sender; the worker
+    /// thread's `recv` loop then sees a closed channel and exits.
+    pub fn remove(&self, id: &str) -> bool {
+        self.sessions.lock().unwrap().remove(id).is_some()
+    }
+
+    /// Drops every session that has been untouched for `ttl` or longer.
+    pub fn reap_idle(&self, ttl: Duration) {
+        self.sessions.lock().unwrap().retain(|_, handle| handle.idle_for() < ttl);
+    }
+}
+
+fn session_worker(program: String, commands: std_mpsc::Receiver<SessionCommand>, metrics: Arc<Metrics>) {
+    let streams = StreamConfig::in_memory();
+    let mut machine = MachineBuilder::new().with_streams(streams).build();
+    machine.consult_module_string("user", &program);
+
+    while let Ok(command) = commands.recv() {
+        match command {
+            SessionCommand::Query { query, max_inferences, max_results, respond_to } => {
+                let result = run_session_query(&mut machine, &query, max_inferences, max_results, &metrics);
+                let _ = respond_to.send(result);
+            }
+        }
+    }
+}
+
+fn run_session_query(
+    machine: &mut Machine,
+    query: &str,
+    max_inferences: Option<u64>,
+    max_results: Option<usize>,
+    metrics: &Metrics,
+) -> Result<(Vec<Value>, Option<usize>), String> {
+    let goal = bound_goal(query, max_inferences);
+    let query_iter = machine.run_query(&goal);
+    let mut results = Vec::new();
+    let mut truncated_limit = None;
+
+    for answer in query_iter {
+        if let Some(limit) = max_results {
+            if results.len() >= limit {
+                truncated_limit = Some(limit);
+                break;
+            }
+        }
+
+        match answer {
+            Ok(LeafAnswer::True) => {
+                metrics.record_answer_true();
+                results.push(json!({ "result": true }));
+            }
+            Ok(LeafAnswer::False) => {
+                metrics.record_answer_false();
+                results.push(json!({ "result": false }));
+            }
+            Ok(LeafAnswer::Exception(term)) => {
+                metrics.record_answer_exception();
+                results.push(json!({ "exception": term_to_json(&term) }));
+            }
+            Ok(LeafAnswer::LeafAnswer { bindings, .. }) => {
+                results.push(convert_bindings_to_json(bindings))
+            }
+            Err(e) => {
+                metrics.record_answer_error();
+                results.push(json!({ "error": format!("{:?}", e) }));
+            }
+        }
+    }
+
+    Ok((results, truncated_limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::Semaphore;
+
+    fn test_metrics() -> Arc<Metrics> {
+        Metrics::new(Arc::new(Semaphore::new(1)), 1)
+    }
+
+    #[test]
+    fn create_rejects_once_the_global_cap_is_hit() {
+        let registry = SessionRegistry::new(1, 10);
+        assert!(registry.create("true.".to_string(), test_metrics(), None).is_ok());
+        assert!(registry.create("true.".to_string(), test_metrics(), None).is_err());
+    }
+
+    #[test]
+    fn create_rejects_once_a_token_hits_its_own_cap() {
+        let registry = SessionRegistry::new(10, 1);
+        assert!(registry.create("true.".to_string(), test_metrics(), Some("tok".to_string())).is_ok());
+        assert!(registry.create("true.".to_string(), test_metrics(), Some("tok".to_string())).is_err());
+        assert!(registry.create("true.".to_string(), test_metrics(), Some("other".to_string())).is_ok());
+    }
+
+    #[test]
+    fn create_enforces_the_cap_under_concurrent_callers() {
+        let registry = SessionRegistry::new(5, 100);
+
+        let callers: Vec<_> = (0..20)
+            .map(|_| {
+                let registry = registry.clone();
+                std::thread::spawn(move || registry.create("true.".to_string(), test_metrics(), None).is_ok())
+            })
+            .collect();
+
+        let successes = callers.into_iter().map(|h| h.join().unwrap()).filter(|ok| *ok).count();
+        assert_eq!(successes, 5);
+    }
+
+    #[test]
+    fn reap_idle_drops_sessions_idle_for_at_least_the_ttl() {
+        let registry = SessionRegistry::new(10, 10);
+        let id = registry.create("true.".to_string(), test_metrics(), None).unwrap();
+        assert!(registry.get(&id).is_some());
+
+        registry.reap_idle(Duration::from_secs(0));
+
+        assert!(registry.get(&id).is_none());
+    }
+}